@@ -1,5 +1,5 @@
 use anyhow::{bail, Context, Result};
-use image::RgbImage;
+use image::{GrayImage, Luma, RgbImage};
 use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use std::fs;
@@ -30,29 +30,6 @@ fn apply_transform_to_buffer(src: &RgbImage, dst: &mut RgbImage, a: i64, b: i64)
     });
 }
 
-// 通过交换缓冲区避免在循环中重复分配内存
-fn arnold_decode(image: &RgbImage, shuffle_times: u32, a: i64, b: i64) -> RgbImage {
-    if shuffle_times == 0 {
-        return image.clone();
-    }
-    
-    let (width, height) = image.dimensions();
-
-    let mut buffer1 = image.clone();
-    let mut buffer2 = RgbImage::new(width, height);
-
-    let mut src = &mut buffer1;
-    let mut dst = &mut buffer2;
-
-    for _ in 0..shuffle_times {
-        apply_transform_to_buffer(src, dst, a, b);
-        mem::swap(&mut src, &mut dst);
-    }
-
-    src.clone()
-}
-
-
 fn parse_path_input(input: &str) -> PathBuf {
     let trimmed = input.trim();
     let dequoted = trimmed.trim_matches(|c| c == '\"' || c == '\'');
@@ -125,7 +102,573 @@ fn calculate_smoothness_score(image: &RgbImage) -> f64 {
     total_diff as f64 / num_comparisons as f64
 }
 
-// 分析输出目录中的所有图像，并根据平滑度得分排序，列出最可能的结果
+// Felzenszwalb-Huttenlocher 图分割评分中控制区域粒度的常数：k 越大，合并出的区域越大
+const SEGMENTATION_K: f64 = 300.0;
+
+// 基于 Felzenszwalb-Huttenlocher 图分割的区域一致性评分。
+// 把每个像素当作图中的节点，使用按 RGB 欧氏距离加权的 8-邻接边，
+// 将所有边按权重升序处理，并用并查集合并区域：每个连通分量记录其内部差异
+// Int(C)（已合并的最大边权），当边权 w ≤ min(Int(C1)+k/|C1|, Int(C2)+k/|C2|) 时合并。
+// 处理完所有边后，得分即剩余分割区域的数量：正确解码的自然图像会坍缩为少数大的
+// 连通区域，而被打乱的图像会碎裂成成千上万个微小区域，因此数量越少越可能是正确结果。
+fn calculate_segmentation_score(image: &RgbImage, k: f64) -> f64 {
+    let (width, height) = image.dimensions();
+    if width < 2 || height < 2 {
+        return f64::MAX;
+    }
+    let w = width as usize;
+    let h = height as usize;
+    let n = w * h;
+
+    // 只向右、下、右下、右上四个方向建边，避免无向边被重复加入
+    let offsets = [(1i32, 0i32), (0, 1), (1, 1), (1, -1)];
+    let mut edges: Vec<(f32, u32, u32)> = Vec::with_capacity(n * offsets.len());
+    for y in 0..h {
+        for x in 0..w {
+            let p1 = image.get_pixel(x as u32, y as u32);
+            let idx1 = (y * w + x) as u32;
+            for (dx, dy) in offsets {
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
+                    continue;
+                }
+                let p2 = image.get_pixel(nx as u32, ny as u32);
+                let dr = p1[0] as f32 - p2[0] as f32;
+                let dg = p1[1] as f32 - p2[1] as f32;
+                let db = p1[2] as f32 - p2[2] as f32;
+                let weight = (dr * dr + dg * dg + db * db).sqrt();
+                let idx2 = (ny as usize * w + nx as usize) as u32;
+                edges.push((weight, idx1, idx2));
+            }
+        }
+    }
+
+    edges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    // 带路径压缩的并查集，额外跟踪每个分量的大小与内部差异 Int(C)
+    let mut parent: Vec<u32> = (0..n as u32).collect();
+    let mut rank = vec![0u8; n];
+    let mut size = vec![1u32; n];
+    let mut int_diff = vec![0f32; n];
+    let mut segments = n;
+
+    fn find(parent: &mut [u32], mut x: u32) -> u32 {
+        while parent[x as usize] != x {
+            parent[x as usize] = parent[parent[x as usize] as usize];
+            x = parent[x as usize];
+        }
+        x
+    }
+
+    let k = k as f32;
+    for (weight, a, b) in edges {
+        let ra = find(&mut parent, a);
+        let rb = find(&mut parent, b);
+        if ra == rb {
+            continue;
+        }
+        let thresh_a = int_diff[ra as usize] + k / size[ra as usize] as f32;
+        let thresh_b = int_diff[rb as usize] + k / size[rb as usize] as f32;
+        if weight <= thresh_a.min(thresh_b) {
+            // 按秩合并，较小的树挂到较大的树下
+            let (big, small) = if rank[ra as usize] >= rank[rb as usize] {
+                (ra, rb)
+            } else {
+                (rb, ra)
+            };
+            parent[small as usize] = big;
+            size[big as usize] += size[small as usize];
+            int_diff[big as usize] = weight.max(int_diff[ra as usize]).max(int_diff[rb as usize]);
+            if rank[ra as usize] == rank[rb as usize] {
+                rank[big as usize] += 1;
+            }
+            segments -= 1;
+        }
+    }
+
+    segments as f64
+}
+
+// 基于 Sobel 梯度的评分：对每个通道用 Gx=[[-1,0,1],[-2,0,2],[-1,0,1]] 及其转置 Gy 卷积，
+// 取幅值 |Gx|+|Gy| 并在通道间求和得到每像素梯度强度。与其比较平均梯度，不如衡量梯度分布的
+// 集中程度：自然图像的强边稀疏、呈重尾分布，而被 Arnold 打乱的图像几乎处处是高梯度。
+// 这里构建 256 桶的梯度幅值直方图并返回其 Shannon 熵——熵越低说明结构越强，越可能是正确结果。
+fn calculate_sobel_entropy_score(image: &RgbImage) -> f64 {
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return f64::MAX;
+    }
+
+    let mut histogram = [0u64; 256];
+    let mut total: u64 = 0;
+
+    // 内边界遍历，跳过没有完整 3x3 邻域的最外圈像素
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let mut magnitude = 0.0f32;
+            for c in 0..3 {
+                let at = |dx: i32, dy: i32| -> f32 {
+                    image.get_pixel((x as i32 + dx) as u32, (y as i32 + dy) as u32)[c] as f32
+                };
+                let gx = -at(-1, -1) + at(1, -1) - 2.0 * at(-1, 0) + 2.0 * at(1, 0)
+                    - at(-1, 1)
+                    + at(1, 1);
+                let gy = -at(-1, -1) - 2.0 * at(0, -1) - at(1, -1)
+                    + at(-1, 1)
+                    + 2.0 * at(0, 1)
+                    + at(1, 1);
+                magnitude += gx.abs() + gy.abs();
+            }
+            // 取三通道幅值之和的平均（除以 3）并截断到 [0,255] 落入直方图，饱和的强边集中在高位桶
+            let bin = (magnitude / 3.0).min(255.0) as usize;
+            histogram[bin] += 1;
+            total += 1;
+        }
+    }
+
+    if total == 0 {
+        return f64::MAX;
+    }
+
+    let total = total as f64;
+    let mut entropy = 0.0f64;
+    for &count in histogram.iter() {
+        if count > 0 {
+            let p = count as f64 / total;
+            entropy -= p * p.log2();
+        }
+    }
+    entropy
+}
+
+// 将图像转换为 8 位灰度并统计 256 桶直方图，供 Otsu 等基于灰度分布的评分使用
+fn grayscale_histogram(image: &RgbImage) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        // 采用 Rec.601 亮度加权
+        let gray = (0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64)
+            .round() as usize;
+        histogram[gray.min(255)] += 1;
+    }
+    histogram
+}
+
+// Otsu 法：遍历每个阈值 t，计算前景/背景权重 ω0,ω1、均值 μ0,μ1 以及类间方差
+// σ_b²(t)=ω0·ω1·(μ0−μ1)²，返回使 σ_b² 最大的阈值及对应的最大类间方差。
+fn otsu(histogram: &[u64; 256]) -> (u8, f64) {
+    let total: u64 = histogram.iter().sum();
+    if total == 0 {
+        return (0, 0.0);
+    }
+    let total = total as f64;
+
+    let sum_all: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| i as f64 * c as f64)
+        .sum();
+
+    let mut weight_bg = 0.0f64;
+    let mut sum_bg = 0.0f64;
+    let mut best_threshold = 0u8;
+    let mut best_variance = 0.0f64;
+
+    for t in 0..256 {
+        weight_bg += histogram[t] as f64;
+        if weight_bg == 0.0 {
+            continue;
+        }
+        let weight_fg = total - weight_bg;
+        if weight_fg == 0.0 {
+            break;
+        }
+        sum_bg += t as f64 * histogram[t] as f64;
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let diff = mean_bg - mean_fg;
+        let variance = (weight_bg / total) * (weight_fg / total) * diff * diff;
+        if variance > best_variance {
+            best_variance = variance;
+            best_threshold = t as u8;
+        }
+    }
+
+    (best_threshold, best_variance)
+}
+
+// van Herk / Gil-Werman 的一维滑动窗口 min/max：无论窗口大小都 O(1) 均摊。
+// 将序列按窗口长度 k 分块，构建从各块起点出发的前向累计数组 g 与到各块终点的后向
+// 累计数组 h，则窗口 [i, i+k-1] 的极值为 max/min(h[i], g[i+k-1])。两端用端点值复制填充。
+fn van_herk_1d(input: &[u8], k: usize, is_max: bool) -> Vec<u8> {
+    let n = input.len();
+    if k <= 1 || n == 0 {
+        return input.to_vec();
+    }
+    let r = (k - 1) / 2;
+    let m = n + 2 * r;
+
+    let pick = |a: u8, b: u8| if is_max { a.max(b) } else { a.min(b) };
+    let sample = |i: usize| -> u8 {
+        // 复制填充：越界位置取最近的端点像素
+        if i < r {
+            input[0]
+        } else if i - r >= n {
+            input[n - 1]
+        } else {
+            input[i - r]
+        }
+    };
+
+    let mut g = vec![0u8; m]; // 从块起点到 i 的累计极值
+    let mut h = vec![0u8; m]; // 从 i 到块终点的累计极值
+    for i in 0..m {
+        g[i] = if i % k == 0 {
+            sample(i)
+        } else {
+            pick(g[i - 1], sample(i))
+        };
+    }
+    for i in (0..m).rev() {
+        h[i] = if i % k == k - 1 || i == m - 1 {
+            sample(i)
+        } else {
+            pick(h[i + 1], sample(i))
+        };
+    }
+
+    (0..n).map(|j| pick(h[j], g[j + k - 1])).collect()
+}
+
+// 用可分离的矩形结构元做一次形态学 min/max（先水平后垂直），每通道独立处理
+fn morph_pass(image: &RgbImage, radius: usize, is_max: bool) -> RgbImage {
+    let k = 2 * radius + 1;
+    let (width, height) = image.dimensions();
+    let mut out = image.clone();
+
+    for y in 0..height {
+        for c in 0..3 {
+            let row: Vec<u8> = (0..width).map(|x| out.get_pixel(x, y)[c]).collect();
+            let res = van_herk_1d(&row, k, is_max);
+            for x in 0..width {
+                out.get_pixel_mut(x, y)[c] = res[x as usize];
+            }
+        }
+    }
+    for x in 0..width {
+        for c in 0..3 {
+            let col: Vec<u8> = (0..height).map(|y| out.get_pixel(x, y)[c]).collect();
+            let res = van_herk_1d(&col, k, is_max);
+            for y in 0..height {
+                out.get_pixel_mut(x, y)[c] = res[y as usize];
+            }
+        }
+    }
+    out
+}
+
+// 形态学去噪：开运算（腐蚀后膨胀）去亮噪点，再闭运算（膨胀后腐蚀）去暗噪点，
+// 从而压制椒盐噪声对邻域差异类评分的干扰。仅作用于打分用的临时副本，绝不改动已保存的 PNG。
+fn denoise(image: &RgbImage, radius: usize) -> RgbImage {
+    if radius == 0 {
+        return image.clone();
+    }
+    let opened = morph_pass(&morph_pass(image, radius, false), radius, true);
+    morph_pass(&morph_pass(&opened, radius, true), radius, false)
+}
+
+// 块伪影检测所尝试的候选块大小
+const BLOCK_SIZES: [u32; 4] = [8, 16, 32, 64];
+
+// 在灰度强度上构建求和面积表 S 与平方和面积表 S2（均在顶部补一行 0、左侧补一列 0），
+// 之后任意矩形的和/平方和都是 O(1) 的四角查询，用于计算矩形的均值与方差。
+fn integral_images(image: &RgbImage) -> (usize, usize, Vec<f64>, Vec<f64>) {
+    let (width, height) = image.dimensions();
+    let w = width as usize;
+    let h = height as usize;
+    let stride = w + 1;
+    let mut s = vec![0f64; stride * (h + 1)];
+    let mut s2 = vec![0f64; stride * (h + 1)];
+
+    for y in 0..h {
+        for x in 0..w {
+            let pixel = image.get_pixel(x as u32, y as u32);
+            let gray =
+                0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64;
+            let i = (y + 1) * stride + (x + 1);
+            s[i] = gray + s[i - 1] + s[i - stride] - s[i - stride - 1];
+            s2[i] = gray * gray + s2[i - 1] + s2[i - stride] - s2[i - stride - 1];
+        }
+    }
+    (w, h, s, s2)
+}
+
+// 四角查询矩形 [x0,x1]×[y0,y1]（闭区间）内像素的和
+fn rect_sum(table: &[f64], stride: usize, x0: usize, y0: usize, x1: usize, y1: usize) -> f64 {
+    let a = (y1 + 1) * stride + (x1 + 1);
+    let b = y0 * stride + (x1 + 1);
+    let c = (y1 + 1) * stride + x0;
+    let d = y0 * stride + x0;
+    table[a] - table[b] - table[c] + table[d]
+}
+
+// 基于面积表检测块状置乱：把图像按候选块大小铺格，比较块间接缝的对比度与块内方差。
+// 正确的按块解码在真实块大小处接缝能量最低；返回接缝对比最强（最可疑）的块大小及其
+// 归一化接缝得分——得分越低说明越不像被块级置乱，越可能是正确结果。
+fn block_seam_detect(image: &RgbImage) -> (u32, f64) {
+    let (w, h, s, s2) = integral_images(image);
+    let stride = w + 1;
+
+    let mean_var = |x0: usize, y0: usize, x1: usize, y1: usize| -> (f64, f64) {
+        let area = ((x1 - x0 + 1) * (y1 - y0 + 1)) as f64;
+        let mean = rect_sum(&s, stride, x0, y0, x1, y1) / area;
+        let var = rect_sum(&s2, stride, x0, y0, x1, y1) / area - mean * mean;
+        (mean, var.max(0.0))
+    };
+
+    let mut best = (0u32, 0.0f64);
+    for &block in BLOCK_SIZES.iter() {
+        let b = block as usize;
+        let nbx = w / b;
+        let nby = h / b;
+        if nbx < 2 && nby < 2 {
+            continue;
+        }
+
+        let mut seam_energy = 0.0f64;
+        let mut seam_count = 0u64;
+        let mut intra_var = 0.0f64;
+        let mut block_count = 0u64;
+
+        for by in 0..nby {
+            for bx in 0..nbx {
+                let x0 = bx * b;
+                let y0 = by * b;
+                let (_, var) = mean_var(x0, y0, x0 + b - 1, y0 + b - 1);
+                intra_var += var;
+                block_count += 1;
+
+                // 与右侧相邻块之间的竖直接缝：比较接缝两侧单列条带的均值
+                if bx + 1 < nbx {
+                    let seam = x0 + b;
+                    let (ml, _) = mean_var(seam - 1, y0, seam - 1, y0 + b - 1);
+                    let (mr, _) = mean_var(seam, y0, seam, y0 + b - 1);
+                    seam_energy += (ml - mr) * (ml - mr);
+                    seam_count += 1;
+                }
+                // 与下方相邻块之间的水平接缝
+                if by + 1 < nby {
+                    let seam = y0 + b;
+                    let (mt, _) = mean_var(x0, seam - 1, x0 + b - 1, seam - 1);
+                    let (mb, _) = mean_var(x0, seam, x0 + b - 1, seam);
+                    seam_energy += (mt - mb) * (mt - mb);
+                    seam_count += 1;
+                }
+            }
+        }
+
+        if seam_count == 0 || block_count == 0 {
+            continue;
+        }
+        let mean_seam = seam_energy / seam_count as f64;
+        let mean_intra = intra_var / block_count as f64;
+        // 接缝对比相对块内方差越大，越像块级置乱的伪影
+        let ratio = mean_seam / (mean_intra + 1.0);
+        if ratio > best.1 {
+            best = (block, ratio);
+        }
+    }
+
+    best
+}
+
+// 评分器接口：把原先硬编码的平滑度评分抽象为可插拔的多种评分方式
+trait Scorer: Sync {
+    // 展示给用户的名称
+    fn name(&self) -> &'static str;
+    // 对单张图像打分
+    fn score(&self, image: &RgbImage) -> f64;
+    // 该评分是否“越高越好”（否则越低越好）
+    fn higher_is_better(&self) -> bool;
+    // 可选：生成便于肉眼确认解码结果的预览图（如 Otsu 二值化），默认不生成
+    fn preview(&self, _image: &RgbImage) -> Option<GrayImage> {
+        None
+    }
+}
+
+// 相邻像素差异的 TV 平滑度（越低越好）
+struct TvSmoothness;
+impl Scorer for TvSmoothness {
+    fn name(&self) -> &'static str {
+        "平滑度(TV)"
+    }
+    fn score(&self, image: &RgbImage) -> f64 {
+        calculate_smoothness_score(image)
+    }
+    fn higher_is_better(&self) -> bool {
+        false
+    }
+}
+
+// Felzenszwalb 图分割区域数量（越少越好）
+struct GraphSegmentation {
+    k: f64,
+}
+impl Scorer for GraphSegmentation {
+    fn name(&self) -> &'static str {
+        "图分割"
+    }
+    fn score(&self, image: &RgbImage) -> f64 {
+        calculate_segmentation_score(image, self.k)
+    }
+    fn higher_is_better(&self) -> bool {
+        false
+    }
+}
+
+// Sobel 梯度直方图的 Shannon 熵（越低越好）
+struct SobelEntropy;
+impl Scorer for SobelEntropy {
+    fn name(&self) -> &'static str {
+        "Sobel熵"
+    }
+    fn score(&self, image: &RgbImage) -> f64 {
+        calculate_sobel_entropy_score(image)
+    }
+    fn higher_is_better(&self) -> bool {
+        false
+    }
+}
+
+// Otsu 最大类间方差，衡量直方图的双峰性（越高越好）
+// 适合 QR 码、黑白文字、旗帜等高对比度的正确解码
+struct OtsuBimodality;
+impl Scorer for OtsuBimodality {
+    fn name(&self) -> &'static str {
+        "Otsu双峰"
+    }
+    fn score(&self, image: &RgbImage) -> f64 {
+        let (_, variance) = otsu(&grayscale_histogram(image));
+        variance
+    }
+    fn higher_is_better(&self) -> bool {
+        true
+    }
+    fn preview(&self, image: &RgbImage) -> Option<GrayImage> {
+        let (threshold, _) = otsu(&grayscale_histogram(image));
+        let (width, height) = image.dimensions();
+        let binarized = GrayImage::from_fn(width, height, |x, y| {
+            let pixel = image.get_pixel(x, y);
+            let gray = 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64;
+            if gray.round() as u8 > threshold {
+                Luma([255u8])
+            } else {
+                Luma([0u8])
+            }
+        });
+        Some(binarized)
+    }
+}
+
+// 基于面积表的块接缝能量（越低越好），用于识别块级（而非逐像素）置乱的正确解码
+struct BlockArtifact;
+impl Scorer for BlockArtifact {
+    fn name(&self) -> &'static str {
+        "块接缝"
+    }
+    fn score(&self, image: &RgbImage) -> f64 {
+        let (_, ratio) = block_seam_detect(image);
+        ratio
+    }
+    fn higher_is_better(&self) -> bool {
+        false
+    }
+}
+
+// 所有可用的评分器，顺序即为下方打分与展示的列顺序
+fn all_scorers() -> Vec<Box<dyn Scorer>> {
+    vec![
+        Box::new(TvSmoothness),
+        Box::new(GraphSegmentation { k: SEGMENTATION_K }),
+        Box::new(SobelEntropy),
+        Box::new(OtsuBimodality),
+        Box::new(BlockArtifact),
+    ]
+}
+
+// 排序所依据的选择：单一评分器，或所有评分器归一化后的等权组合
+#[derive(Clone, Copy)]
+enum RankChoice {
+    Single(usize),
+    Weighted,
+}
+
+// 让用户选择排序所用的评分方式
+fn get_rank_choice(scorers: &[Box<dyn Scorer>]) -> Result<RankChoice> {
+    loop {
+        println!("📊 请选择用于排序的评分方式:");
+        for (i, scorer) in scorers.iter().enumerate() {
+            println!("   {}) {}", i + 1, scorer.name());
+        }
+        println!("   {}) 加权组合 (各评分归一化后等权求和)", scorers.len() + 1);
+        print!("   - 请输入编号 (默认 1): ");
+        io::stdout().flush()?;
+        let input = read_line_from_stdin()?;
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return Ok(RankChoice::Single(0));
+        }
+        if let Ok(n) = trimmed.parse::<usize>() {
+            if n >= 1 && n <= scorers.len() {
+                return Ok(RankChoice::Single(n - 1));
+            }
+            if n == scorers.len() + 1 {
+                return Ok(RankChoice::Weighted);
+            }
+        }
+        println!("🤔 请输入 1 到 {} 之间的编号", scorers.len() + 1);
+    }
+}
+
+// 让用户选择去噪强度（结构元半径，0 表示关闭），去噪只作用于打分用的副本
+fn get_denoise_radius() -> Result<usize> {
+    loop {
+        print!("🧽 打分前形态学去噪半径 (0 关闭，默认 0): ");
+        io::stdout().flush()?;
+        let input = read_line_from_stdin()?;
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return Ok(0);
+        }
+        if let Ok(r) = trimmed.parse::<usize>() {
+            return Ok(r);
+        }
+        println!("🤔 请输入一个非负整数");
+    }
+}
+
+// 把一列原始得分归一化为 [0,1] 的“劣度”，0 表示该评分下最佳，便于跨评分器加权比较
+fn normalize_badness(values: &[f64], higher_is_better: bool) -> Vec<f64> {
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = max - min;
+    values
+        .iter()
+        .map(|&v| {
+            if !v.is_finite() || span <= 0.0 {
+                return 1.0;
+            }
+            if higher_is_better {
+                (max - v) / span
+            } else {
+                (v - min) / span
+            }
+        })
+        .collect()
+}
+
+// 分析输出目录中的所有图像，并根据所选评分方式排序，列出最可能的结果
 fn analyze_results(output_dir: &Path) -> Result<()> {
     let entries = fs::read_dir(output_dir)
         .with_context(|| format!("❌ 无法读取分析目录: {:?}", output_dir))?
@@ -138,39 +681,118 @@ fn analyze_results(output_dir: &Path) -> Result<()> {
         return Ok(());
     }
 
+    let scorers = all_scorers();
+    let choice = get_rank_choice(&scorers)?;
+    let denoise_radius = get_denoise_radius()?;
+
     let bar_style = ProgressStyle::default_bar()
         .template("{spinner:.cyan} [{elapsed_precise}] [{bar:40.yellow/red}] {pos}/{len} ({percent}%)  分析中: {msg}")
         .unwrap()
         .progress_chars("=> ");
     let bar = ProgressBar::new(entries.len() as u64).with_style(bar_style);
 
-    let mut scored_images: Vec<(PathBuf, f64)> = entries
+    // 每个候选文件都算出全部评分器的原始得分，便于交叉核对模棱两可的解码结果
+    let scored_images: Vec<(PathBuf, Vec<f64>)> = entries
         .par_iter()
         .progress_with(bar)
         .filter_map(|entry| {
             let path = entry.path();
             if let Ok(image) = image::open(&path) {
-                let score = calculate_smoothness_score(&image.to_rgb8());
-                Some((path, score))
+                // 在临时副本上去噪后打分，真正残留少量椒盐噪声的正确解码也能胜出
+                let scored_on = denoise(&image.to_rgb8(), denoise_radius);
+                let raw = scorers.iter().map(|s| s.score(&scored_on)).collect();
+                Some((path, raw))
             } else {
                 None
             }
         })
         .collect();
 
-    // 根据平滑度进行升序排序，得分越低越好
-    scored_images.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-    
-    println!("\n🔍 分析完成，以下是可能性最高的 5 个结果 (得分越低越可能是正确结果):");
+    // 依据所选方式计算每个候选的排序键：单一评分器按其方向，加权组合则归一化后求和
+    let ranking_key: Vec<f64> = match choice {
+        RankChoice::Single(idx) => {
+            let column: Vec<f64> = scored_images.iter().map(|(_, raw)| raw[idx]).collect();
+            normalize_badness(&column, scorers[idx].higher_is_better())
+        }
+        RankChoice::Weighted => {
+            let mut combined = vec![0.0f64; scored_images.len()];
+            for (idx, scorer) in scorers.iter().enumerate() {
+                let column: Vec<f64> = scored_images.iter().map(|(_, raw)| raw[idx]).collect();
+                for (acc, badness) in combined
+                    .iter_mut()
+                    .zip(normalize_badness(&column, scorer.higher_is_better()))
+                {
+                    *acc += badness / scorers.len() as f64;
+                }
+            }
+            combined
+        }
+    };
+
+    let mut order: Vec<usize> = (0..scored_images.len()).collect();
+    // 排序键越低（越接近各评分下的最佳）越可能是正确结果
+    order.sort_by(|&a, &b| {
+        ranking_key[a]
+            .partial_cmp(&ranking_key[b])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    println!("\n🔍 分析完成，以下是可能性最高的 5 个结果 (排序键越低越可能是正确结果):");
     println!("---------------------------------------------------------------------------------");
-    
-    for (path, score) in scored_images.iter().take(5) {
+    // 表头：文件名 + 排序键 + 各评分器原始得分
+    print!("   {:<22} | {:>9}", "文件", "排序键");
+    for scorer in scorers.iter() {
+        print!(" | {:>10}", scorer.name());
+    }
+    println!();
+    println!("---------------------------------------------------------------------------------");
+
+    for &i in order.iter().take(5) {
+        let (path, raw) = &scored_images[i];
         if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-            println!("   - 📄 文件: {:<25} | 📉 得分: {:.2}", filename, score);
+            print!("   📄 {:<18} | {:>9.3}", filename, ranking_key[i]);
+            for value in raw.iter() {
+                print!(" | {:>10.2}", value);
+            }
+            println!();
         }
     }
     println!("---------------------------------------------------------------------------------");
-    
+
+    // 额外报告前几名的块级置乱线索：最可疑的块大小及其归一化接缝得分
+    println!("🧩 块伪影检测 (接缝得分越低越不像被块级置乱):");
+    for &i in order.iter().take(5) {
+        let path = &scored_images[i].0;
+        if let (Some(filename), Ok(image)) = (
+            path.file_name().and_then(|s| s.to_str()),
+            image::open(path),
+        ) {
+            let (block, ratio) = block_seam_detect(&denoise(&image.to_rgb8(), denoise_radius));
+            if block == 0 {
+                println!("   - 📄 {:<22} | 块大小: 无 (图像过小)", filename);
+            } else {
+                println!("   - 📄 {:<22} | 最佳块大小: {:<3} | 接缝得分: {:.3}", filename, block, ratio);
+            }
+        }
+    }
+    println!("---------------------------------------------------------------------------------");
+
+    // 若按单一评分器排序且其能生成预览（如 Otsu 二值化），为前几名各输出一张预览图，
+    // 方便用户立即读出解码得到的 QR 码 / flag
+    if let RankChoice::Single(idx) = choice {
+        for &i in order.iter().take(5) {
+            let path = &scored_images[i].0;
+            let Ok(image) = image::open(path) else { continue };
+            if let Some(preview) = scorers[idx].preview(&image.to_rgb8()) {
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("preview");
+                let preview_path = output_dir.join(format!("{}_otsu.png", stem));
+                if preview.save(&preview_path).is_ok() {
+                    println!("   🖼️  已生成预览: {:?}", preview_path);
+                }
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -232,16 +854,20 @@ fn main() -> Result<()> {
     println!("🚀 输出结果将保存在: {:?}", output_dir);
     println!();
 
-    let mut params = Vec::new();
-    for st in shuffle_times_range {
-        for a in a_values_range.clone() {
-            for b in b_values_range.clone() {
-                params.push((st as u32, a, b));
-            }
+    // 变换次数范围的下/上界（闭区间）。负的下界没有意义，钳到 0
+    let st_lo = shuffle_times_range.start.max(0);
+    let st_hi = shuffle_times_range.end - 1;
+
+    // 参数对 (a,b) 才是真正的并行粒度：对每个 (a,b) 只推进一条变换链，
+    // 沿途保存落在变换次数范围内的中间结果，而不再为每个次数从头重算
+    let mut ab_pairs = Vec::new();
+    for a in a_values_range.clone() {
+        for b in b_values_range.clone() {
+            ab_pairs.push((a, b));
         }
     }
-    
-    if params.is_empty() {
+
+    if ab_pairs.is_empty() || st_hi < st_lo {
         println!("🤷‍♀️ 没有有效的参数组合");
         return Ok(());
     }
@@ -250,23 +876,64 @@ fn main() -> Result<()> {
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)  ETA: {eta}")
         .unwrap()
         .progress_chars("#>-");
-    let bar = ProgressBar::new(params.len() as u64).with_style(bar_style);
+    let bar = ProgressBar::new(ab_pairs.len() as u64).with_style(bar_style);
 
     let start_time = std::time::Instant::now();
+    let (width, height) = encoded_image.dimensions();
 
-    params
+    // 每个 (a,b) 返回检测到的 Arnold 周期（若在 st_hi 之前回到原图）
+    let mut periods: Vec<(i64, i64, Option<i64>)> = ab_pairs
         .into_par_iter()
         .progress_with(bar)
-        .for_each(|(shuffle_times, a, b)| {
-            let decoded_image = arnold_decode(&encoded_image, shuffle_times, a, b);
-            let output_filename = format!("{}_{}_{}.png", shuffle_times, a, b);
-            let output_path = output_dir.join(output_filename);
-            decoded_image.save(output_path).ok();
-        });
+        .map(|(a, b)| {
+            let save = |times: i64, img: &RgbImage| {
+                let output_path = output_dir.join(format!("{}_{}_{}.png", times, a, b));
+                img.save(output_path).ok();
+            };
+
+            // step 0 即原图本身，若在范围内直接保存
+            if st_lo <= 0 {
+                save(0, &encoded_image);
+            }
+
+            // 复用两个缓冲区交替推进，每次只做一步变换
+            let mut current = encoded_image.clone();
+            let mut scratch = RgbImage::new(width, height);
+            let mut detected = None;
+
+            for step in 1..=st_hi {
+                apply_transform_to_buffer(&current, &mut scratch, a, b);
+                mem::swap(&mut current, &mut scratch);
+
+                // 与原图逐像素比较（首个不同像素即可提前退出）；一旦相同则说明
+                // 回到起点，之后的变换次数只会循环重复，无需继续推进
+                if current.as_raw() == encoded_image.as_raw() {
+                    detected = Some(step);
+                    break;
+                }
+
+                if step >= st_lo {
+                    save(step, &current);
+                }
+            }
+
+            (a, b, detected)
+        })
+        .collect();
 
     let duration = start_time.elapsed();
     println!("\n⏱️ 用时: {:.2} 秒", duration.as_secs_f64());
 
+    // 汇报每个 (a,b) 的真实周期，帮助用户了解密钥空间、避免请求超过一个完整周期的次数
+    periods.sort();
+    println!("🔁 检测到的 Arnold 周期 (变换 period 次后回到原图):");
+    for (a, b, detected) in &periods {
+        match detected {
+            Some(p) => println!("   - a={:<3} b={:<3} | 周期: {}", a, b, p),
+            None => println!("   - a={:<3} b={:<3} | 在 {} 次内未检测到周期", a, b, st_hi),
+        }
+    }
+
     println!("🎉 处理完成");
     
     if let Err(e) = analyze_results(&output_dir) {